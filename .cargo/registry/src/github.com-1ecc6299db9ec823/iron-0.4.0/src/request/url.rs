@@ -1,7 +1,10 @@
 //! HTTP/HTTPS URL type for Iron.
 
-use url::{self, Host};
+use url::{self, Host, Origin};
+use std::collections::HashMap;
+use std::error::Error;
 use std::fmt;
+use std::str::FromStr;
 
 /// HTTP/HTTPS URL type for Iron.
 #[derive(PartialEq, Eq, Clone, Debug)]
@@ -115,6 +118,160 @@ impl Url {
     pub fn fragment(&self) -> Option<&str> {
         self.generic_url.fragment()
     }
+
+    /// The decoded query string as a list of `(key, value)` pairs.
+    ///
+    /// The pairs are percent- and form-decoded in the order they appear
+    /// in the query string. An absent query string yields an empty vector.
+    pub fn query_pairs(&self) -> Vec<(String, String)> {
+        match self.generic_url.query() {
+            Some(query) => url::form_urlencoded::parse(query.as_bytes())
+                .into_owned()
+                .collect(),
+            None => Vec::new()
+        }
+    }
+
+    /// A map view of the query string, collapsing duplicate keys.
+    ///
+    /// Keys that appear more than once are collapsed so that the *last*
+    /// occurrence wins, mirroring the common "treat query keys as a map"
+    /// use case. The map is built on demand, so handlers that never call
+    /// this pay nothing for it.
+    pub fn query_unique(&self) -> HashMap<String, String> {
+        let mut map = HashMap::new();
+        for (key, value) in self.query_pairs() {
+            map.insert(key, value);
+        }
+        map
+    }
+
+    /// Whether the query string contains the given key.
+    pub fn query_unique_contains(&self, key: &str) -> bool {
+        self.query_pairs().iter().any(|&(ref k, _)| k == key)
+    }
+
+    /// The last value associated with `key` in the query string.
+    ///
+    /// `None` if the key does not appear. Mirrors the "last occurrence wins"
+    /// rule of `query_unique` without materializing the whole map.
+    pub fn query_unique_get(&self, key: &str) -> Option<String> {
+        self.query_pairs()
+            .into_iter()
+            .rev()
+            .find(|&(ref k, _)| k == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Set the host of this URL.
+    ///
+    /// A value of `None` removes the host, which rust-url rejects for the
+    /// special schemes Iron supports (they always require a host), so such a
+    /// call errors and leaves `self` untouched. The result is otherwise
+    /// re-validated through the same checks as `from_generic_url`.
+    pub fn set_host(&mut self, host: Option<&str>) -> Result<(), String> {
+        let mut raw_url = self.generic_url.clone();
+        try!(raw_url.set_host(host).map_err(|e| format!("{}", e)));
+        *self = try!(Url::from_generic_url(raw_url));
+        Ok(())
+    }
+
+    /// Set the port of this URL.
+    ///
+    /// A value of `None` falls back to the scheme's default port. Leaving the
+    /// URL without a known port is rejected; on error `self` is unchanged.
+    pub fn set_port(&mut self, port: Option<u16>) -> Result<(), String> {
+        let mut raw_url = self.generic_url.clone();
+        try!(raw_url.set_port(port).map_err(|_| {
+            format!("Cannot set port on `{}`", self.generic_url.scheme())
+        }));
+        *self = try!(Url::from_generic_url(raw_url));
+        Ok(())
+    }
+
+    /// Set the path of this URL.
+    pub fn set_path(&mut self, path: &str) -> Result<(), String> {
+        let mut raw_url = self.generic_url.clone();
+        raw_url.set_path(path);
+        *self = try!(Url::from_generic_url(raw_url));
+        Ok(())
+    }
+
+    /// Set the query string of this URL.
+    ///
+    /// A value of `None` removes the query string.
+    pub fn set_query(&mut self, query: Option<&str>) -> Result<(), String> {
+        let mut raw_url = self.generic_url.clone();
+        raw_url.set_query(query);
+        *self = try!(Url::from_generic_url(raw_url));
+        Ok(())
+    }
+
+    /// Set the fragment of this URL.
+    ///
+    /// A value of `None` removes the fragment.
+    pub fn set_fragment(&mut self, fragment: Option<&str>) -> Result<(), String> {
+        let mut raw_url = self.generic_url.clone();
+        raw_url.set_fragment(fragment);
+        *self = try!(Url::from_generic_url(raw_url));
+        Ok(())
+    }
+
+    /// Set the username of this URL.
+    pub fn set_username(&mut self, username: &str) -> Result<(), String> {
+        let mut raw_url = self.generic_url.clone();
+        try!(raw_url.set_username(username).map_err(|_| {
+            format!("Cannot set username on `{}`", self.generic_url.scheme())
+        }));
+        *self = try!(Url::from_generic_url(raw_url));
+        Ok(())
+    }
+
+    /// Set the password of this URL.
+    ///
+    /// A value of `None` removes the password.
+    pub fn set_password(&mut self, password: Option<&str>) -> Result<(), String> {
+        let mut raw_url = self.generic_url.clone();
+        try!(raw_url.set_password(password).map_err(|_| {
+            format!("Cannot set password on `{}`", self.generic_url.scheme())
+        }));
+        *self = try!(Url::from_generic_url(raw_url));
+        Ok(())
+    }
+
+    /// Resolve a relative reference against this URL.
+    ///
+    /// The `input` is parsed with `self` as the base, following the usual
+    /// base-relative rules (`"../api/v2"`, `"/resources/x.js"`, and so on),
+    /// and the result is re-validated through `from_generic_url`.
+    ///
+    /// Joining an empty string returns a URL equal to `self`. Joining an
+    /// absolute URL with a non-special scheme errors with the usual
+    /// "Not a special scheme" message.
+    pub fn join(&self, input: &str) -> Result<Url, String> {
+        match self.generic_url.join(input) {
+            Ok(raw_url) => Url::from_generic_url(raw_url),
+            Err(e) => Err(format!("{}", e))
+        }
+    }
+
+    /// The origin of this URL: the scheme, host, and port tuple.
+    ///
+    /// Ports are normalized to the scheme's default, so `https://x:443` and
+    /// `https://x` share an origin. This mirrors the default-port logic in
+    /// `port()`.
+    pub fn origin(&self) -> Origin {
+        self.generic_url.origin()
+    }
+
+    /// Whether this URL has the same origin as `other`.
+    ///
+    /// Two URLs are same-origin when their scheme, host, and port (after
+    /// default-port normalization) match. This is the check web handlers
+    /// need for CORS and referer-style decisions.
+    pub fn same_origin_as(&self, other: &Url) -> bool {
+        self.origin() == other.origin()
+    }
 }
 
 impl fmt::Display for Url {
@@ -124,6 +281,50 @@ impl fmt::Display for Url {
     }
 }
 
+/// The error produced when a string cannot be parsed as an Iron `Url`.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ParseError {
+    message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.message.fmt(formatter)
+    }
+}
+
+impl Error for ParseError {
+    fn description(&self) -> &str {
+        &self.message
+    }
+}
+
+impl FromStr for Url {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Url, ParseError> {
+        Url::parse(input).map_err(|message| ParseError { message: message })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl ::serde::Serialize for Url {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: ::serde::Serializer {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> ::serde::Deserialize<'de> for Url {
+    fn deserialize<D>(deserializer: D) -> Result<Url, D::Error>
+        where D: ::serde::Deserializer<'de> {
+        use serde::de::Error;
+        let string = try!(<String as ::serde::Deserialize>::deserialize(deserializer));
+        Url::parse(&string).map_err(D::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::Url;
@@ -200,4 +401,119 @@ mod test {
         let parsed = Url::parse("https://example.com:443").unwrap().to_string();
         assert_eq!(parsed, "https://example.com/");
     }
+
+    #[test]
+    fn test_query_pairs() {
+        let url = Url::parse("http://example.com/?a=1&b=two&a=3").unwrap();
+        assert_eq!(url.query_pairs(),
+                   vec![("a".to_string(), "1".to_string()),
+                        ("b".to_string(), "two".to_string()),
+                        ("a".to_string(), "3".to_string())]);
+
+        assert!(Url::parse("http://example.com/").unwrap().query_pairs().is_empty());
+    }
+
+    #[test]
+    fn test_query_unique_last_wins() {
+        let url = Url::parse("http://example.com/?a=1&b=two&a=3").unwrap();
+        assert_eq!(url.query_unique_get("a").unwrap(), "3");
+        assert_eq!(url.query_unique_get("b").unwrap(), "two");
+        assert!(url.query_unique_contains("a"));
+        assert!(!url.query_unique_contains("missing"));
+        assert!(url.query_unique_get("missing").is_none());
+    }
+
+    #[test]
+    fn test_set_host_and_port() {
+        let mut url = Url::parse("http://example.com/path").unwrap();
+        url.set_host(Some("iron.rs")).unwrap();
+        url.set_port(Some(8080)).unwrap();
+        assert_eq!(url.to_string(), "http://iron.rs:8080/path");
+    }
+
+    #[test]
+    fn test_set_path_query_fragment() {
+        let mut url = Url::parse("http://example.com/").unwrap();
+        url.set_path("/a/b").unwrap();
+        url.set_query(Some("q=wow")).unwrap();
+        url.set_fragment(Some("frag")).unwrap();
+        assert_eq!(url.to_string(), "http://example.com/a/b?q=wow#frag");
+    }
+
+    #[test]
+    fn test_set_userinfo() {
+        let mut url = Url::parse("http://example.com/").unwrap();
+        url.set_username("john").unwrap();
+        url.set_password(Some("pass")).unwrap();
+        assert_eq!(url.username().unwrap(), "john");
+        assert_eq!(url.password().unwrap(), "pass");
+    }
+
+    #[test]
+    fn test_set_host_rejects_empty() {
+        let mut url = Url::parse("http://example.com/").unwrap();
+        assert!(url.set_host(None).is_err());
+        // The failed mutation left the URL untouched.
+        assert_eq!(url.to_string(), "http://example.com/");
+    }
+
+    #[test]
+    fn test_join_relative() {
+        let base = Url::parse("http://example.com/api/v1/users").unwrap();
+        assert_eq!(base.join("../v2/items").unwrap().to_string(),
+                   "http://example.com/api/v2/items");
+        assert_eq!(base.join("/resources/x.js").unwrap().to_string(),
+                   "http://example.com/resources/x.js");
+    }
+
+    #[test]
+    fn test_join_empty_is_equal() {
+        let base = Url::parse("http://example.com/path?q=wow").unwrap();
+        assert_eq!(base.join("").unwrap(), base);
+    }
+
+    #[test]
+    fn test_join_non_special_scheme() {
+        let base = Url::parse("http://example.com/").unwrap();
+        assert!(base.join("mailto:bob@example.com").is_err());
+    }
+
+    #[test]
+    fn test_same_origin_default_port() {
+        let explicit = Url::parse("https://example.com:443/a").unwrap();
+        let implicit = Url::parse("https://example.com/b").unwrap();
+        assert!(explicit.same_origin_as(&implicit));
+        assert_eq!(explicit.origin(), implicit.origin());
+    }
+
+    #[test]
+    fn test_different_origin() {
+        let http = Url::parse("http://example.com/").unwrap();
+        let https = Url::parse("https://example.com/").unwrap();
+        let other_host = Url::parse("https://other.com/").unwrap();
+        assert!(!http.same_origin_as(&https));
+        assert!(!https.same_origin_as(&other_host));
+    }
+
+    #[test]
+    fn test_from_str() {
+        let url: Url = "http://example.com/wow".parse().unwrap();
+        assert_eq!(url, Url::parse("http://example.com/wow").unwrap());
+    }
+
+    #[test]
+    fn test_from_str_error() {
+        let result: Result<Url, _> = "not a url".parse::<Url>();
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        extern crate serde_test;
+        use self::serde_test::{assert_tokens, Token};
+
+        let url = Url::parse("http://example.com/path?q=wow").unwrap();
+        assert_tokens(&url, &[Token::Str("http://example.com/path?q=wow")]);
+    }
 }
\ No newline at end of file